@@ -0,0 +1,78 @@
+// Copyright (c) Mysten Labs, Inc.
+// SPDX-License-Identifier: Apache-2.0
+
+//! A pluggable cache for on-chain package bytecode fetched while verifying dependencies. Because a
+//! published package object is immutable at a given version, a cache keyed by [`ObjectRef`] can
+//! never go stale, so a hit is always safe to trust without talking to the fullnode again.
+
+use std::path::PathBuf;
+
+use async_trait::async_trait;
+use move_binary_format::CompiledModule;
+
+use sui_types::base_types::ObjectRef;
+
+/// A cache of previously fetched on-chain package bytecode, keyed by the immutable [`ObjectRef`]
+/// it was read at.
+#[async_trait]
+pub trait PackageCache: Send + Sync {
+    /// Return the cached modules for `object_ref`, if present.
+    async fn get(&self, object_ref: &ObjectRef) -> Option<Vec<CompiledModule>>;
+
+    /// Record `modules` as the contents of the package at `object_ref`.
+    async fn put(&self, object_ref: &ObjectRef, modules: &[CompiledModule]);
+}
+
+/// A [`PackageCache`] backed by a directory on the local filesystem, with one file per cached
+/// package, named after its object id and version.
+pub struct FsPackageCache {
+    root: PathBuf,
+}
+
+impl FsPackageCache {
+    pub fn new(root: impl Into<PathBuf>) -> Self {
+        Self { root: root.into() }
+    }
+
+    fn entry_path(&self, object_ref: &ObjectRef) -> PathBuf {
+        let (object_id, version, _) = object_ref;
+        self.root.join(format!("{object_id}-{}", version.value()))
+    }
+}
+
+#[async_trait]
+impl PackageCache for FsPackageCache {
+    async fn get(&self, object_ref: &ObjectRef) -> Option<Vec<CompiledModule>> {
+        let path = self.entry_path(object_ref);
+        let bytes = tokio::fs::read(&path).await.ok()?;
+        let module_bytes: Vec<Vec<u8>> = bcs::from_bytes(&bytes).ok()?;
+
+        module_bytes
+            .iter()
+            .map(|bytes| CompiledModule::deserialize_with_defaults(bytes).ok())
+            .collect()
+    }
+
+    async fn put(&self, object_ref: &ObjectRef, modules: &[CompiledModule]) {
+        let Ok(()) = tokio::fs::create_dir_all(&self.root).await else {
+            return;
+        };
+
+        let module_bytes: Vec<Vec<u8>> = modules
+            .iter()
+            .map(|module| {
+                let mut bytes = Vec::new();
+                module
+                    .serialize(&mut bytes)
+                    .expect("serializing a compiled module cannot fail");
+                bytes
+            })
+            .collect();
+
+        let Ok(bytes) = bcs::to_bytes(&module_bytes) else {
+            return;
+        };
+
+        let _ = tokio::fs::write(self.entry_path(object_ref), bytes).await;
+    }
+}
@@ -0,0 +1,615 @@
+// Copyright (c) Mysten Labs, Inc.
+// SPDX-License-Identifier: Apache-2.0
+
+use std::collections::BTreeMap;
+use std::path::Path;
+
+use futures::stream::{self, StreamExt};
+use move_binary_format::CompiledModule;
+use move_core_types::account_address::AccountAddress;
+use move_symbol_pool::Symbol;
+use thiserror::Error;
+
+use sui_framework_build::compiled_package::CompiledPackage;
+use sui_sdk::apis::ReadApi;
+use sui_sdk::error::Error as SdkError;
+use sui_types::base_types::{ObjectID, ObjectRef};
+
+mod cache;
+mod lock;
+mod store;
+
+pub use cache::{FsPackageCache, PackageCache};
+pub use store::{PackageStore, SnapshotPackageStore};
+
+use lock::{hash_module, VerifiedLock};
+use store::RpcPackageStore;
+
+#[cfg(test)]
+mod tests;
+
+/// How to treat the root package (the package currently being built) during verification: either
+/// skip checking it against its on-chain counterpart entirely, or compare it like any other
+/// dependency.
+#[derive(PartialEq, Eq, Clone, Copy)]
+pub enum SourceMode {
+    Skip,
+    Verify,
+}
+
+/// The number of distinct on-chain dependency packages fetched concurrently by
+/// [`BytecodeSourceVerifier::verify_package_deps`], unless overridden with
+/// [`BytecodeSourceVerifier::with_concurrency_limit`].
+const DEFAULT_CONCURRENCY_LIMIT: usize = 8;
+
+/// A locally compiled module, together with the name of the package it was compiled as part of.
+struct LocalModule<'m> {
+    package: Symbol,
+    module: &'m CompiledModule,
+}
+
+/// Like [`LocalModule`], but owning its module outright so it can be moved into a concurrent
+/// fetch-and-compare task.
+struct OwnedModule {
+    package: Symbol,
+    module: CompiledModule,
+}
+
+/// Checks that the bytecode of a locally compiled Move package matches the bytecode of the same
+/// package already published on chain, by fetching the on-chain package(s) through a
+/// [`PackageStore`] and comparing normalized modules one-by-one.
+pub struct BytecodeSourceVerifier<'a> {
+    pub verbose: bool,
+    store: Box<dyn PackageStore + 'a>,
+    cache: Option<Box<dyn PackageCache>>,
+    concurrency: usize,
+}
+
+impl<'a> BytecodeSourceVerifier<'a> {
+    pub fn new(read_api: &'a ReadApi, verbose: bool) -> Self {
+        Self {
+            verbose,
+            store: Box::new(RpcPackageStore { read_api }),
+            cache: None,
+            concurrency: DEFAULT_CONCURRENCY_LIMIT,
+        }
+    }
+
+    /// Construct a verifier that consults `cache` before fetching an on-chain dependency, and
+    /// populates it after a successful fetch. Safe to share across verification runs -- an
+    /// on-chain package is immutable at a given version, so a cache hit never goes stale.
+    pub fn new_with_cache(
+        read_api: &'a ReadApi,
+        verbose: bool,
+        cache: Box<dyn PackageCache>,
+    ) -> Self {
+        Self {
+            verbose,
+            store: Box::new(RpcPackageStore { read_api }),
+            cache: Some(cache),
+            concurrency: DEFAULT_CONCURRENCY_LIMIT,
+        }
+    }
+
+    /// Construct a verifier that reads on-chain packages from `store` instead of a live fullnode
+    /// -- e.g. a [`SnapshotPackageStore`] for offline verification.
+    pub fn with_store(store: Box<dyn PackageStore + 'a>, verbose: bool) -> Self {
+        Self {
+            verbose,
+            store,
+            cache: None,
+            concurrency: DEFAULT_CONCURRENCY_LIMIT,
+        }
+    }
+
+    /// Override the number of distinct on-chain dependency packages that
+    /// [`Self::verify_package_deps`] will fetch concurrently. Defaults to
+    /// [`DEFAULT_CONCURRENCY_LIMIT`].
+    pub fn with_concurrency_limit(mut self, limit: usize) -> Self {
+        self.concurrency = limit.max(1);
+        self
+    }
+
+    /// Verify `pkg`'s dependencies (if `verify_deps` is set) and/or its root modules (according to
+    /// `source_mode`) against their on-chain counterparts.
+    ///
+    /// `source_mode` controls whether the root package itself is checked: `SourceMode::Skip`
+    /// leaves it unverified (useful when the package hasn't been published yet, or its address is
+    /// not yet known), while `SourceMode::Verify` expects `pkg`'s self address to already match an
+    /// on-chain package.
+    pub async fn verify_package(
+        &self,
+        pkg: &CompiledPackage,
+        verify_deps: bool,
+        source_mode: SourceMode,
+    ) -> Result<(), SourceVerificationError> {
+        if verify_deps {
+            self.verify_package_deps(pkg).await?;
+        }
+
+        if let SourceMode::Verify = source_mode {
+            let address = pkg.package.compiled_package_info.address;
+            self.verify_package_root(pkg, address).await?;
+        }
+
+        Ok(())
+    }
+
+    /// Verify that the root modules of `pkg` match the on-chain modules published at `address`.
+    pub async fn verify_package_root(
+        &self,
+        pkg: &CompiledPackage,
+        address: AccountAddress,
+    ) -> Result<(), SourceVerificationError> {
+        if address == AccountAddress::ZERO {
+            return Err(SourceVerificationError::ZeroOnChainAddresSpecifiedFailure);
+        }
+
+        let root_name = pkg.package.compiled_package_info.package_name;
+        let locals: Vec<LocalModule> = pkg
+            .package
+            .root_modules()
+            .map(|unit| LocalModule {
+                package: root_name,
+                module: &unit.unit.module,
+            })
+            .collect();
+
+        let on_chain = self.on_chain_modules(address).await?;
+        compare_modules(address, &locals, &on_chain)
+    }
+
+    /// Verify that each of `pkg`'s (on-chain) dependencies match the modules published at the
+    /// address recorded in `pkg`'s dependency graph.
+    ///
+    /// Distinct on-chain dependency packages are fetched concurrently, up to
+    /// [`Self::concurrency`] at a time, and each fetch's (CPU-bound) bytecode comparison is
+    /// offloaded to a blocking task so it overlaps with the other fetches' network latency.
+    pub async fn verify_package_deps(
+        &self,
+        pkg: &CompiledPackage,
+    ) -> Result<(), SourceVerificationError> {
+        stream::iter(dependency_modules_by_address(pkg))
+            .map(|(address, locals)| async move {
+                let on_chain = self.on_chain_modules(address).await?;
+                tokio::task::spawn_blocking(move || {
+                    compare_modules_owned(address, &locals, &on_chain)
+                })
+                .await
+                .expect("bytecode comparison task panicked")
+            })
+            .buffer_unordered(self.concurrency)
+            .collect::<Vec<Result<(), SourceVerificationError>>>()
+            .await
+            .into_iter()
+            .collect::<Result<(), SourceVerificationError>>()
+    }
+
+    /// Like [`Self::verify_package_deps`], but instead of returning on the first discrepancy,
+    /// keeps going and collects every mismatch found across all dependencies.
+    async fn verify_package_deps_collect(
+        &self,
+        pkg: &CompiledPackage,
+    ) -> Vec<SourceVerificationError> {
+        stream::iter(dependency_modules_by_address(pkg))
+            .map(|(address, locals)| async move {
+                let on_chain = match self.on_chain_modules(address).await {
+                    Ok(on_chain) => on_chain,
+                    Err(e) => return vec![e],
+                };
+                tokio::task::spawn_blocking(move || {
+                    compare_modules_collect(address, &locals, &on_chain)
+                })
+                .await
+                .expect("bytecode comparison task panicked")
+            })
+            .buffer_unordered(self.concurrency)
+            .collect::<Vec<Vec<SourceVerificationError>>>()
+            .await
+            .into_iter()
+            .flatten()
+            .collect()
+    }
+
+    /// Verify `pkg`'s dependencies (if `verify_deps` is set) and/or its root modules (according to
+    /// `source_mode`), but unlike [`Self::verify_package`], don't stop at the first discrepancy:
+    /// collect every mismatch found across the whole package so a caller can report them all in
+    /// one pass.
+    pub async fn verify_package_collect(
+        &self,
+        pkg: &CompiledPackage,
+        verify_deps: bool,
+        source_mode: SourceMode,
+    ) -> Result<(), Vec<SourceVerificationError>> {
+        let mut errors = Vec::new();
+
+        if verify_deps {
+            errors.extend(self.verify_package_deps_collect(pkg).await);
+        }
+
+        if let SourceMode::Verify = source_mode {
+            let address = pkg.package.compiled_package_info.address;
+            if address == AccountAddress::ZERO {
+                errors.push(SourceVerificationError::ZeroOnChainAddresSpecifiedFailure);
+            } else {
+                let root_name = pkg.package.compiled_package_info.package_name;
+                let locals: Vec<OwnedModule> = pkg
+                    .package
+                    .root_modules()
+                    .map(|unit| OwnedModule {
+                        package: root_name,
+                        module: unit.unit.module.clone(),
+                    })
+                    .collect();
+
+                match self.on_chain_modules(address).await {
+                    Ok(on_chain) => {
+                        errors.extend(compare_modules_collect(address, &locals, &on_chain))
+                    }
+                    Err(e) => errors.push(e),
+                }
+            }
+        }
+
+        if errors.is_empty() {
+            Ok(())
+        } else {
+            Err(errors)
+        }
+    }
+
+    /// Verify both `pkg`'s root modules (against `root_on_chain_address`) and its dependencies.
+    pub async fn verify_package_root_and_deps(
+        &self,
+        pkg: &CompiledPackage,
+        root_on_chain_address: AccountAddress,
+    ) -> Result<(), SourceVerificationError> {
+        self.verify_package_deps(pkg).await?;
+        self.verify_package_root(pkg, root_on_chain_address).await
+    }
+
+    /// Verify `pkg`'s dependencies like [`Self::verify_package_deps`], but consult (and update) a
+    /// lockfile at `lock_path` so that a dependency whose on-chain [`ObjectRef`] and chain
+    /// identifier haven't changed since the last successful run is checked by comparing bytecode
+    /// hashes alone, without re-fetching its modules from the fullnode.
+    pub async fn verify_package_deps_with_lock(
+        &self,
+        pkg: &CompiledPackage,
+        lock_path: &Path,
+    ) -> Result<(), SourceVerificationError> {
+        let mut lock = VerifiedLock::read(lock_path)?;
+        let chain_id = self.store.chain_identifier().await?;
+
+        let mut by_address: BTreeMap<AccountAddress, Vec<LocalModule>> = BTreeMap::new();
+        for (package, unit) in &pkg.package.deps_compiled_units {
+            let module = &unit.unit.module;
+            by_address
+                .entry(*module.self_id().address())
+                .or_default()
+                .push(LocalModule {
+                    package: *package,
+                    module,
+                });
+        }
+
+        for (address, locals) in by_address {
+            let current_ref = self.object_ref(address).await?;
+
+            if let Some(entry) = lock.entry(address) {
+                if entry.chain_id == chain_id && entry.object_ref == current_ref {
+                    self.verify_against_lock(address, &locals, &entry)?;
+                    continue;
+                }
+            }
+
+            let on_chain = self.on_chain_modules(address).await?;
+            compare_modules(address, &locals, &on_chain)?;
+            lock.set_entry(address, current_ref, chain_id.clone(), &on_chain);
+        }
+
+        lock.write(lock_path)
+    }
+
+    /// Verify both `pkg`'s lockfile-aware dependency check and its root modules.
+    pub async fn verify_package_root_and_deps_with_lock(
+        &self,
+        pkg: &CompiledPackage,
+        root_on_chain_address: AccountAddress,
+        lock_path: &Path,
+    ) -> Result<(), SourceVerificationError> {
+        self.verify_package_deps_with_lock(pkg, lock_path).await?;
+        self.verify_package_root(pkg, root_on_chain_address).await
+    }
+
+    fn verify_against_lock(
+        &self,
+        address: AccountAddress,
+        locals: &[LocalModule],
+        entry: &lock::LockEntry,
+    ) -> Result<(), SourceVerificationError> {
+        for local in locals {
+            let name = Symbol::from(local.module.self_id().name().as_str());
+            let local_hash = hash_module(local.module);
+
+            match entry.module_hashes.get(&name) {
+                Some(hash) if *hash == local_hash => continue,
+                _ => {
+                    return Err(SourceVerificationError::LockfileMismatch {
+                        address,
+                        package: local.package,
+                        module: name,
+                    })
+                }
+            }
+        }
+
+        // The lock only proves the on-chain object hasn't changed -- it says nothing about
+        // whether the local copy has shrunk since it was recorded, so check the reverse direction
+        // too, same as `compare_modules` does for a live fetch.
+        for name in entry.module_hashes.keys() {
+            let found_locally = locals
+                .iter()
+                .any(|local| local.module.self_id().name().as_str() == name.as_str());
+
+            if !found_locally {
+                return Err(SourceVerificationError::LocalDependencyNotFound {
+                    address,
+                    module: *name,
+                });
+            }
+        }
+
+        Ok(())
+    }
+
+    /// Fetch just the current [`ObjectRef`] (id, version, digest) of the on-chain object at
+    /// `address`, without downloading its contents. Used to cheaply check whether a locked
+    /// dependency is still current before deciding whether a full fetch is needed.
+    async fn object_ref(
+        &self,
+        address: AccountAddress,
+    ) -> Result<ObjectRef, SourceVerificationError> {
+        self.store.object_ref(address).await
+    }
+
+    /// Fetch and normalize the on-chain modules published at `address`, keyed by module name.
+    ///
+    /// When a cache is configured, the ref is fetched up front (a cheap, bcs-less round trip) so
+    /// a hit can avoid fetching the modules at all. Without a cache, that ref-only fetch would be
+    /// pure overhead -- `self.store.raw_modules` already returns the ref as part of the same
+    /// response it needs to fetch anyway, so it's read from there instead.
+    async fn on_chain_modules(
+        &self,
+        address: AccountAddress,
+    ) -> Result<BTreeMap<Symbol, CompiledModule>, SourceVerificationError> {
+        if let Some(cache) = &self.cache {
+            let object_ref = self.object_ref(address).await?;
+            if let Some(modules) = cache.get(&object_ref).await {
+                return Ok(modules
+                    .into_iter()
+                    .map(|m| (Symbol::from(m.self_id().name().as_str()), m))
+                    .collect());
+            }
+        }
+
+        let (object_ref, raw_modules) = self.store.raw_modules(address).await?;
+
+        let modules: BTreeMap<Symbol, CompiledModule> = raw_modules
+            .into_iter()
+            .map(|(name, bytes)| {
+                let module = CompiledModule::deserialize_with_defaults(&bytes).map_err(|e| {
+                    SourceVerificationError::InvalidModuleFailure {
+                        name: name.clone(),
+                        message: e.to_string(),
+                    }
+                })?;
+                Ok((Symbol::from(name), module))
+            })
+            .collect::<Result<_, SourceVerificationError>>()?;
+
+        if let Some(cache) = &self.cache {
+            let values: Vec<CompiledModule> = modules.values().cloned().collect();
+            cache.put(&object_ref, &values).await;
+        }
+
+        Ok(modules)
+    }
+}
+
+impl BytecodeSourceVerifier<'static> {
+    /// Construct a verifier from a store address, dispatching on its URI scheme -- e.g.
+    /// `grpc+https://fullnode.mainnet.sui.io:443` to verify against a live fullnode, or
+    /// `file:///path/to/snapshot` to verify offline against a previously exported snapshot. See
+    /// [`store::from_addr`] for the supported schemes.
+    pub async fn from_addr(addr: &str, verbose: bool) -> Result<Self, SourceVerificationError> {
+        Ok(Self {
+            verbose,
+            store: store::from_addr(addr).await?,
+            cache: None,
+            concurrency: DEFAULT_CONCURRENCY_LIMIT,
+        })
+    }
+}
+
+/// Group `pkg`'s dependency modules by the address they're expected to be published at.
+fn dependency_modules_by_address(
+    pkg: &CompiledPackage,
+) -> BTreeMap<AccountAddress, Vec<OwnedModule>> {
+    let mut by_address: BTreeMap<AccountAddress, Vec<OwnedModule>> = BTreeMap::new();
+    for (package, unit) in &pkg.package.deps_compiled_units {
+        let module = unit.unit.module.clone();
+        by_address
+            .entry(*module.self_id().address())
+            .or_default()
+            .push(OwnedModule {
+                package: *package,
+                module,
+            });
+    }
+    by_address
+}
+
+/// Compare a set of locally compiled modules, all expected to live on chain at `address`, against
+/// the on-chain modules actually found there.
+fn compare_modules(
+    address: AccountAddress,
+    local_modules: &[LocalModule],
+    on_chain_modules: &BTreeMap<Symbol, CompiledModule>,
+) -> Result<(), SourceVerificationError> {
+    for local in local_modules {
+        let name = Symbol::from(local.module.self_id().name().as_str());
+
+        let Some(on_chain_module) = on_chain_modules.get(&name) else {
+            return Err(SourceVerificationError::OnChainDependencyNotFound {
+                package: local.package,
+                module: name,
+            });
+        };
+
+        if local.module != on_chain_module {
+            return Err(SourceVerificationError::ModuleBytecodeMismatch {
+                address,
+                package: local.package,
+                module: name,
+            });
+        }
+    }
+
+    for name in on_chain_modules.keys() {
+        let found_locally = local_modules
+            .iter()
+            .any(|local| local.module.self_id().name().as_str() == name.as_str());
+
+        if !found_locally {
+            return Err(SourceVerificationError::LocalDependencyNotFound {
+                address,
+                module: *name,
+            });
+        }
+    }
+
+    Ok(())
+}
+
+/// Equivalent to [`compare_modules`], but for a set of [`OwnedModule`]s -- used by the concurrent
+/// dependency verification path, where modules are cloned out of the package so they can be moved
+/// into a blocking task.
+fn compare_modules_owned(
+    address: AccountAddress,
+    local_modules: &[OwnedModule],
+    on_chain_modules: &BTreeMap<Symbol, CompiledModule>,
+) -> Result<(), SourceVerificationError> {
+    let locals: Vec<LocalModule> = local_modules
+        .iter()
+        .map(|local| LocalModule {
+            package: local.package,
+            module: &local.module,
+        })
+        .collect();
+
+    compare_modules(address, &locals, on_chain_modules)
+}
+
+/// Like [`compare_modules_owned`], but instead of returning on the first discrepancy, checks every
+/// module and returns all the mismatches found.
+fn compare_modules_collect(
+    address: AccountAddress,
+    local_modules: &[OwnedModule],
+    on_chain_modules: &BTreeMap<Symbol, CompiledModule>,
+) -> Vec<SourceVerificationError> {
+    let mut errors = Vec::new();
+
+    for local in local_modules {
+        let name = Symbol::from(local.module.self_id().name().as_str());
+
+        let Some(on_chain_module) = on_chain_modules.get(&name) else {
+            errors.push(SourceVerificationError::OnChainDependencyNotFound {
+                package: local.package,
+                module: name,
+            });
+            continue;
+        };
+
+        if &local.module != on_chain_module {
+            errors.push(SourceVerificationError::ModuleBytecodeMismatch {
+                address,
+                package: local.package,
+                module: name,
+            });
+        }
+    }
+
+    for name in on_chain_modules.keys() {
+        let found_locally = local_modules
+            .iter()
+            .any(|local| local.module.self_id().name().as_str() == name.as_str());
+
+        if !found_locally {
+            errors.push(SourceVerificationError::LocalDependencyNotFound {
+                address,
+                module: *name,
+            });
+        }
+    }
+
+    errors
+}
+
+#[derive(Debug, Error)]
+pub enum SourceVerificationError {
+    #[error("Could not read dependency object: {0}")]
+    DependencyObjectReadFailure(SdkError),
+
+    #[error("On-chain address cannot be zero")]
+    ZeroOnChainAddresSpecifiedFailure,
+
+    #[error("Could not resolve on-chain object: {0}")]
+    SuiObjectRefFailure(anyhow::Error),
+
+    #[error("Invalid module {name}: {message}")]
+    InvalidModuleFailure { name: String, message: String },
+
+    #[error("On chain object at {0} not found")]
+    OnChainObjectNotFound(ObjectID),
+
+    #[error("Found object {1} when expecting a package at {0}")]
+    ObjectFoundWhenPackageExpected(ObjectID, ObjectID),
+
+    #[error("Local version of dependency {address}::{module} was not found")]
+    LocalDependencyNotFound {
+        address: AccountAddress,
+        module: Symbol,
+    },
+
+    #[error("On-chain version of dependency {package}::{module} was not found")]
+    OnChainDependencyNotFound { package: Symbol, module: Symbol },
+
+    #[error("Module bytecode mismatch for dependency {package}::{module} at {address}")]
+    ModuleBytecodeMismatch {
+        address: AccountAddress,
+        package: Symbol,
+        module: Symbol,
+    },
+
+    #[error(
+        "Locked bytecode hash for dependency {package}::{module} at {address} does not match \
+         freshly compiled bytecode, despite the on-chain object version being unchanged"
+    )]
+    LockfileMismatch {
+        address: AccountAddress,
+        package: Symbol,
+        module: Symbol,
+    },
+
+    #[error("Could not read or write verification lockfile: {0}")]
+    LockfileIoFailure(String),
+
+    #[error("Unsupported package store scheme: {0}")]
+    UnsupportedStoreScheme(String),
+
+    #[error("Snapshot package not found for {0}: {1}")]
+    SnapshotPackageNotFound(AccountAddress, String),
+
+    #[error("Could not read snapshot store: {0}")]
+    SnapshotIoFailure(String),
+}
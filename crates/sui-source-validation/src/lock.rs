@@ -0,0 +1,133 @@
+// Copyright (c) Mysten Labs, Inc.
+// SPDX-License-Identifier: Apache-2.0
+
+//! An opt-in lockfile (`Move.verified.lock` by convention) that records the result of a
+//! successful verification run, keyed by the on-chain address of each dependency. On a later run,
+//! a dependency whose locked [`ObjectRef`] (including version) and chain identifier still match
+//! can be verified by comparing bytecode hashes alone, without re-fetching the package from the
+//! fullnode.
+
+use std::collections::BTreeMap;
+use std::fs;
+use std::path::Path;
+
+use move_binary_format::CompiledModule;
+use move_core_types::account_address::AccountAddress;
+use move_symbol_pool::Symbol;
+use serde::{Deserialize, Serialize};
+use sha2::{Digest, Sha256};
+
+use sui_types::base_types::{ObjectRef, SequenceNumber};
+
+use crate::SourceVerificationError;
+
+/// The on-disk representation of a verification lockfile.
+#[derive(Debug, Default, Serialize, Deserialize)]
+pub struct VerifiedLock {
+    package: BTreeMap<String, LockedPackage>,
+}
+
+#[derive(Debug, Clone, Serialize, Deserialize)]
+struct LockedPackage {
+    object_id: String,
+    version: u64,
+    digest: String,
+    chain_id: String,
+    modules: BTreeMap<String, String>,
+}
+
+/// The locked state of a single on-chain dependency, in a form convenient to compare against a
+/// freshly compiled package.
+pub struct LockEntry {
+    pub object_ref: ObjectRef,
+    pub chain_id: String,
+    pub module_hashes: BTreeMap<Symbol, [u8; 32]>,
+}
+
+impl VerifiedLock {
+    /// Read a lockfile from `path`. Returns an empty lock if the file does not exist -- the
+    /// lockfile is opt-in, so a missing file just means every dependency will be fetched live.
+    pub fn read(path: &Path) -> Result<Self, SourceVerificationError> {
+        if !path.exists() {
+            return Ok(Self::default());
+        }
+
+        let contents = fs::read_to_string(path)
+            .map_err(|e| SourceVerificationError::LockfileIoFailure(e.to_string()))?;
+
+        toml::from_str(&contents)
+            .map_err(|e| SourceVerificationError::LockfileIoFailure(e.to_string()))
+    }
+
+    /// Write this lock out to `path`, overwriting whatever is already there.
+    pub fn write(&self, path: &Path) -> Result<(), SourceVerificationError> {
+        let contents = toml::to_string_pretty(self)
+            .map_err(|e| SourceVerificationError::LockfileIoFailure(e.to_string()))?;
+
+        fs::write(path, contents)
+            .map_err(|e| SourceVerificationError::LockfileIoFailure(e.to_string()))
+    }
+
+    /// Look up the locked entry for `address`, if one is recorded.
+    pub fn entry(&self, address: AccountAddress) -> Option<LockEntry> {
+        let locked = self.package.get(&address.to_canonical_string(true))?;
+
+        let object_id = locked.object_id.parse().ok()?;
+        let version = SequenceNumber::from_u64(locked.version);
+        let digest = locked.digest.parse().ok()?;
+
+        let module_hashes = locked
+            .modules
+            .iter()
+            .map(|(name, hash)| Some((Symbol::from(name.as_str()), parse_hash(hash)?)))
+            .collect::<Option<BTreeMap<_, _>>>()?;
+
+        Some(LockEntry {
+            object_ref: (object_id, version, digest),
+            chain_id: locked.chain_id.clone(),
+            module_hashes,
+        })
+    }
+
+    /// Record (or replace) the verified state of the dependency published at `object_ref`.
+    pub fn set_entry(
+        &mut self,
+        address: AccountAddress,
+        object_ref: ObjectRef,
+        chain_id: String,
+        modules: &BTreeMap<Symbol, CompiledModule>,
+    ) {
+        let (object_id, version, digest) = object_ref;
+        self.package.insert(
+            address.to_canonical_string(true),
+            LockedPackage {
+                object_id: object_id.to_string(),
+                version: version.value(),
+                digest: digest.to_string(),
+                chain_id,
+                modules: modules
+                    .iter()
+                    .map(|(name, module)| (name.to_string(), hex::encode(hash_module(module))))
+                    .collect(),
+            },
+        );
+    }
+}
+
+fn parse_hash(hash: &str) -> Option<[u8; 32]> {
+    let bytes = hex::decode(hash).ok()?;
+    bytes.try_into().ok()
+}
+
+/// Hash the normalized bytecode of `module`, the same representation that's compared for bytecode
+/// equality elsewhere in this crate.
+pub fn hash_module(module: &CompiledModule) -> [u8; 32] {
+    let mut bytes = Vec::new();
+    module
+        .serialize(&mut bytes)
+        .expect("serializing a compiled module cannot fail");
+
+    let mut hasher = Sha256::new();
+    hasher.update(&bytes);
+    hasher.finalize().into()
+}
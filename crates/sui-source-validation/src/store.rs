@@ -0,0 +1,277 @@
+// Copyright (c) Mysten Labs, Inc.
+// SPDX-License-Identifier: Apache-2.0
+
+//! Abstracts over where on-chain package bytecode is actually read from, so that verification can
+//! run either against a live fullnode ([`RpcPackageStore`]) or against a previously exported,
+//! offline snapshot ([`SnapshotPackageStore`]) -- e.g. for CI or air-gapped audits that want to
+//! verify against a pinned, committed copy of a package rather than whatever happens to be on
+//! chain right now.
+
+use std::collections::BTreeMap;
+use std::path::PathBuf;
+
+use async_trait::async_trait;
+use move_core_types::account_address::AccountAddress;
+use serde::{Deserialize, Serialize};
+
+use sui_json_rpc_types::{SuiObjectDataOptions, SuiRawData};
+use sui_sdk::apis::ReadApi;
+use sui_sdk::{SuiClient, SuiClientBuilder};
+use sui_types::base_types::{ObjectID, ObjectRef};
+
+use crate::SourceVerificationError;
+
+/// A source of on-chain package bytecode: either a live fullnode, or an offline snapshot of one.
+#[async_trait]
+pub trait PackageStore: Send + Sync {
+    /// The current [`ObjectRef`] (id, version, digest) of the package published at `address`,
+    /// without downloading its contents.
+    async fn object_ref(
+        &self,
+        address: AccountAddress,
+    ) -> Result<ObjectRef, SourceVerificationError>;
+
+    /// The [`ObjectRef`] and raw (un-deserialized) bytecode of every module in the package at
+    /// `address`, keyed by module name. Returning the ref alongside the modules lets a caller
+    /// that doesn't otherwise need it (e.g. because no cache is configured) avoid a second,
+    /// ref-only round trip just to learn what this call already knows.
+    async fn raw_modules(
+        &self,
+        address: AccountAddress,
+    ) -> Result<(ObjectRef, BTreeMap<String, Vec<u8>>), SourceVerificationError>;
+
+    /// An identifier for the chain this store's data was produced against (e.g. a chain
+    /// identifier digest, or a fixed label for a snapshot).
+    async fn chain_identifier(&self) -> Result<String, SourceVerificationError>;
+}
+
+/// Construct a [`PackageStore`] from a URI, dispatching on its scheme:
+///
+/// - `grpc+http://host:port` / `grpc+https://host:port` -- verify against a live fullnode.
+/// - `file://path/to/snapshot` -- verify against a directory of previously exported packages.
+pub async fn from_addr(addr: &str) -> Result<Box<dyn PackageStore>, SourceVerificationError> {
+    if let Some(rest) = addr.strip_prefix("file://") {
+        return Ok(Box::new(SnapshotPackageStore::new(rest)));
+    }
+
+    if let Some(rest) = addr.strip_prefix("grpc+") {
+        let client = SuiClientBuilder::default()
+            .build(rest)
+            .await
+            .map_err(|e| SourceVerificationError::DependencyObjectReadFailure(e.into()))?;
+        return Ok(Box::new(OwnedRpcPackageStore { client }));
+    }
+
+    Err(SourceVerificationError::UnsupportedStoreScheme(
+        addr.to_string(),
+    ))
+}
+
+/// Fetch the current [`ObjectRef`] of the object at `address` from a [`ReadApi`]. Together with
+/// [`rpc_raw_modules`], this is the logic shared by both RPC store implementations below,
+/// regardless of whether they borrow or own their client.
+async fn rpc_object_ref(
+    read_api: &ReadApi,
+    address: AccountAddress,
+) -> Result<ObjectRef, SourceVerificationError> {
+    let object_id = ObjectID::from(address);
+
+    let object = read_api
+        .get_object_with_options(object_id, SuiObjectDataOptions::new())
+        .await
+        .map_err(SourceVerificationError::DependencyObjectReadFailure)?
+        .into_object()
+        .map_err(|e| SourceVerificationError::SuiObjectRefFailure(e.into()))?;
+
+    Ok(object.object_ref())
+}
+
+async fn rpc_raw_modules(
+    read_api: &ReadApi,
+    address: AccountAddress,
+) -> Result<(ObjectRef, BTreeMap<String, Vec<u8>>), SourceVerificationError> {
+    let object_id = ObjectID::from(address);
+
+    let object = read_api
+        .get_object_with_options(object_id, SuiObjectDataOptions::new().with_bcs())
+        .await
+        .map_err(SourceVerificationError::DependencyObjectReadFailure)?
+        .into_object()
+        .map_err(|e| SourceVerificationError::SuiObjectRefFailure(e.into()))?;
+
+    // The bcs fetch above already returns the object's id/version/digest, so there's no need for
+    // a separate ref-only round trip to get the same information.
+    let object_ref = object.object_ref();
+
+    let Some(raw) = object.bcs else {
+        return Err(SourceVerificationError::OnChainObjectNotFound(object_id));
+    };
+
+    let SuiRawData::Package(raw_pkg) = raw else {
+        return Err(SourceVerificationError::ObjectFoundWhenPackageExpected(
+            object_id,
+            object.object_id,
+        ));
+    };
+
+    Ok((object_ref, raw_pkg.module_map))
+}
+
+/// Reads packages from a live fullnode, via a borrowed [`ReadApi`]. This is the default backend,
+/// constructed implicitly by [`crate::BytecodeSourceVerifier::new`].
+pub(crate) struct RpcPackageStore<'a> {
+    pub(crate) read_api: &'a ReadApi,
+}
+
+#[async_trait]
+impl<'a> PackageStore for RpcPackageStore<'a> {
+    async fn object_ref(
+        &self,
+        address: AccountAddress,
+    ) -> Result<ObjectRef, SourceVerificationError> {
+        rpc_object_ref(self.read_api, address).await
+    }
+
+    async fn raw_modules(
+        &self,
+        address: AccountAddress,
+    ) -> Result<(ObjectRef, BTreeMap<String, Vec<u8>>), SourceVerificationError> {
+        rpc_raw_modules(self.read_api, address).await
+    }
+
+    async fn chain_identifier(&self) -> Result<String, SourceVerificationError> {
+        self.read_api
+            .get_chain_identifier()
+            .await
+            .map_err(SourceVerificationError::DependencyObjectReadFailure)
+    }
+}
+
+/// Like [`RpcPackageStore`], but owns its client instead of borrowing one -- used by
+/// [`from_addr`], which has nothing alive for a borrow to outlive.
+struct OwnedRpcPackageStore {
+    client: SuiClient,
+}
+
+#[async_trait]
+impl PackageStore for OwnedRpcPackageStore {
+    async fn object_ref(
+        &self,
+        address: AccountAddress,
+    ) -> Result<ObjectRef, SourceVerificationError> {
+        rpc_object_ref(self.client.read_api(), address).await
+    }
+
+    async fn raw_modules(
+        &self,
+        address: AccountAddress,
+    ) -> Result<(ObjectRef, BTreeMap<String, Vec<u8>>), SourceVerificationError> {
+        rpc_raw_modules(self.client.read_api(), address).await
+    }
+
+    async fn chain_identifier(&self) -> Result<String, SourceVerificationError> {
+        self.client
+            .read_api()
+            .get_chain_identifier()
+            .await
+            .map_err(SourceVerificationError::DependencyObjectReadFailure)
+    }
+}
+
+/// Reads packages from a directory of previously exported on-chain packages, laid out as:
+///
+/// ```text
+/// <root>/chain_id                           -- plain text chain identifier
+/// <root>/<address>/object_ref.toml          -- { object_id, version, digest }
+/// <root>/<address>/modules/<name>.mv        -- raw module bytecode
+/// ```
+pub struct SnapshotPackageStore {
+    root: PathBuf,
+}
+
+#[derive(Deserialize, Serialize)]
+struct SnapshotObjectRef {
+    object_id: String,
+    version: u64,
+    digest: String,
+}
+
+impl SnapshotPackageStore {
+    pub fn new(root: impl Into<PathBuf>) -> Self {
+        Self { root: root.into() }
+    }
+
+    fn package_dir(&self, address: AccountAddress) -> PathBuf {
+        self.root.join(address.to_canonical_string(true))
+    }
+
+    fn read_object_ref(
+        &self,
+        address: AccountAddress,
+    ) -> Result<ObjectRef, SourceVerificationError> {
+        let path = self.package_dir(address).join("object_ref.toml");
+        let contents = std::fs::read_to_string(&path)
+            .map_err(|e| SourceVerificationError::SnapshotPackageNotFound(address, e.to_string()))?;
+
+        let parsed: SnapshotObjectRef = toml::from_str(&contents)
+            .map_err(|e| SourceVerificationError::SnapshotPackageNotFound(address, e.to_string()))?;
+
+        let object_id = parsed.object_id.parse().map_err(|_| {
+            SourceVerificationError::SnapshotPackageNotFound(address, path.display().to_string())
+        })?;
+        let digest = parsed.digest.parse().map_err(|_| {
+            SourceVerificationError::SnapshotPackageNotFound(address, path.display().to_string())
+        })?;
+
+        Ok((
+            object_id,
+            sui_types::base_types::SequenceNumber::from_u64(parsed.version),
+            digest,
+        ))
+    }
+}
+
+#[async_trait]
+impl PackageStore for SnapshotPackageStore {
+    async fn object_ref(
+        &self,
+        address: AccountAddress,
+    ) -> Result<ObjectRef, SourceVerificationError> {
+        self.read_object_ref(address)
+    }
+
+    async fn raw_modules(
+        &self,
+        address: AccountAddress,
+    ) -> Result<(ObjectRef, BTreeMap<String, Vec<u8>>), SourceVerificationError> {
+        let object_ref = self.read_object_ref(address)?;
+
+        let modules_dir = self.package_dir(address).join("modules");
+        let entries = std::fs::read_dir(&modules_dir)
+            .map_err(|e| SourceVerificationError::SnapshotPackageNotFound(address, e.to_string()))?;
+
+        let mut modules = BTreeMap::new();
+        for entry in entries {
+            let entry = entry.map_err(|e| {
+                SourceVerificationError::SnapshotPackageNotFound(address, e.to_string())
+            })?;
+            let path = entry.path();
+            let Some(name) = path.file_stem().and_then(|s| s.to_str()) else {
+                continue;
+            };
+
+            let bytes = std::fs::read(&path).map_err(|e| {
+                SourceVerificationError::SnapshotPackageNotFound(address, e.to_string())
+            })?;
+            modules.insert(name.to_string(), bytes);
+        }
+
+        Ok((object_ref, modules))
+    }
+
+    async fn chain_identifier(&self) -> Result<String, SourceVerificationError> {
+        std::fs::read_to_string(self.root.join("chain_id"))
+            .map(|s| s.trim().to_string())
+            .map_err(|e| SourceVerificationError::SnapshotIoFailure(e.to_string()))
+    }
+}
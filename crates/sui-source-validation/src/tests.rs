@@ -8,13 +8,16 @@ use std::{path::PathBuf, str};
 use sui::client_commands::WalletContext;
 use sui_framework_build::compiled_package::{BuildConfig, CompiledPackage};
 use sui_types::{
-    base_types::{ObjectRef, SuiAddress},
+    base_types::{ObjectDigest, ObjectRef, SuiAddress},
     SUI_SYSTEM_STATE_OBJECT_ID,
 };
 use test_utils::network::TestClusterBuilder;
 use test_utils::transaction::publish_package_with_wallet;
 
-use crate::{BytecodeSourceVerifier, SourceMode, SourceVerificationError};
+use crate::{
+    BytecodeSourceVerifier, FsPackageCache, PackageCache, PackageStore, SnapshotPackageStore,
+    SourceMode, SourceVerificationError,
+};
 
 #[tokio::test]
 async fn successful_verification() -> anyhow::Result<()> {
@@ -445,6 +448,485 @@ async fn module_bytecode_mismatch() -> anyhow::Result<()> {
     Ok(())
 }
 
+#[tokio::test]
+async fn verify_with_lock_skips_unchanged_dependency() -> anyhow::Result<()> {
+    let mut cluster = TestClusterBuilder::new().build().await?;
+    let sender = cluster.get_address_0();
+    let context = &mut cluster.wallet;
+
+    let b_ref = {
+        let fixtures = tempfile::tempdir()?;
+        let b_src = copy_package(&fixtures, "b", [("b", SuiAddress::ZERO)]).await?;
+        publish_package(context, sender, b_src).await
+    };
+
+    let a_pkg = {
+        let fixtures = tempfile::tempdir()?;
+        let b_id = b_ref.0.into();
+        copy_package(&fixtures, "b", [("b", b_id)]).await?;
+        let a_src = copy_package(&fixtures, "a", [("a", SuiAddress::ZERO), ("b", b_id)]).await?;
+        compile_package(a_src)
+    };
+
+    let client = context.get_client().await?;
+    let verifier = BytecodeSourceVerifier::new(client.read_api(), false);
+
+    let lock_dir = tempfile::tempdir()?;
+    let lock_path = lock_dir.path().join("Move.verified.lock");
+
+    // First run fetches `b` live and populates the lock with its module hashes.
+    verifier
+        .verify_package_deps_with_lock(&a_pkg.package, &lock_path)
+        .await
+        .unwrap();
+    assert!(lock_path.exists());
+
+    // Second run, nothing has changed on chain: verified purely against the lock.
+    verifier
+        .verify_package_deps_with_lock(&a_pkg.package, &lock_path)
+        .await
+        .unwrap();
+
+    Ok(())
+}
+
+#[tokio::test]
+async fn verify_with_lock_detects_stale_local_dependency() -> anyhow::Result<()> {
+    let mut cluster = TestClusterBuilder::new().build().await?;
+    let sender = cluster.get_address_0();
+    let context = &mut cluster.wallet;
+
+    let b_ref = {
+        let fixtures = tempfile::tempdir()?;
+        let b_src = copy_package(&fixtures, "b", [("b", SuiAddress::ZERO)]).await?;
+        publish_package(context, sender, b_src).await
+    };
+
+    let client = context.get_client().await?;
+    let verifier = BytecodeSourceVerifier::new(client.read_api(), false);
+
+    let lock_dir = tempfile::tempdir()?;
+    let lock_path = lock_dir.path().join("Move.verified.lock");
+
+    // Verify once against a complete local copy of `b`, to populate the lock with hashes for
+    // all of its modules.
+    {
+        let fixtures = tempfile::tempdir()?;
+        let b_id = b_ref.0.into();
+        copy_package(&fixtures, "b", [("b", b_id)]).await?;
+        let a_src = copy_package(&fixtures, "a", [("a", SuiAddress::ZERO), ("b", b_id)]).await?;
+        let a_pkg = compile_package(a_src);
+
+        verifier
+            .verify_package_deps_with_lock(&a_pkg.package, &lock_path)
+            .await
+            .unwrap();
+    }
+
+    // Nothing has changed on chain, but the local mirror of `b` is now missing module `d` --
+    // the lock's recorded object ref still matches, so this has to be caught by comparing the
+    // lock's module set against the (now smaller) local one.
+    let a_pkg = {
+        let fixtures = tempfile::tempdir()?;
+        let b_id = b_ref.0.into();
+        let b_src = copy_package(&fixtures, "b", [("b", b_id)]).await?;
+        let a_src = copy_package(&fixtures, "a", [("a", SuiAddress::ZERO), ("b", b_id)]).await?;
+        tokio::fs::remove_file(b_src.join("sources").join("d.move")).await?;
+        compile_package(a_src)
+    };
+
+    let Err(err) = verifier
+        .verify_package_deps_with_lock(&a_pkg.package, &lock_path)
+        .await
+    else {
+        panic!("Expected verification to fail");
+    };
+
+    assert!(matches!(
+        err,
+        SourceVerificationError::LocalDependencyNotFound { .. }
+    ));
+
+    Ok(())
+}
+
+#[tokio::test]
+async fn fs_package_cache_roundtrip() -> anyhow::Result<()> {
+    let mut cluster = TestClusterBuilder::new().build().await?;
+    let sender = cluster.get_address_0();
+    let context = &mut cluster.wallet;
+
+    let b_ref = {
+        let fixtures = tempfile::tempdir()?;
+        let b_src = copy_package(&fixtures, "b", [("b", SuiAddress::ZERO)]).await?;
+        publish_package(context, sender, b_src).await
+    };
+
+    let b_pkg = {
+        let fixtures = tempfile::tempdir()?;
+        let b_src = copy_package(&fixtures, "b", [("b", b_ref.0.into())]).await?;
+        compile_package(b_src)
+    };
+    let modules: Vec<_> = b_pkg
+        .package
+        .root_modules()
+        .map(|unit| unit.unit.module.clone())
+        .collect();
+
+    let cache_dir = tempfile::tempdir()?;
+    let cache = FsPackageCache::new(cache_dir.path());
+
+    // Nothing has been cached yet.
+    assert!(cache.get(&b_ref).await.is_none());
+
+    cache.put(&b_ref, &modules).await;
+
+    let cached = cache
+        .get(&b_ref)
+        .await
+        .expect("cache entry should be present after put");
+    assert_eq!(cached.len(), modules.len());
+
+    Ok(())
+}
+
+#[tokio::test]
+async fn successful_verification_with_cache() -> anyhow::Result<()> {
+    let mut cluster = TestClusterBuilder::new().build().await?;
+    let sender = cluster.get_address_0();
+    let context = &mut cluster.wallet;
+
+    let b_ref = {
+        let fixtures = tempfile::tempdir()?;
+        let b_src = copy_package(&fixtures, "b", [("b", SuiAddress::ZERO)]).await?;
+        publish_package(context, sender, b_src).await
+    };
+
+    let a_pkg = {
+        let fixtures = tempfile::tempdir()?;
+        let b_id = b_ref.0.into();
+        copy_package(&fixtures, "b", [("b", b_id)]).await?;
+        let a_src = copy_package(&fixtures, "a", [("a", SuiAddress::ZERO), ("b", b_id)]).await?;
+        compile_package(a_src)
+    };
+
+    let client = context.get_client().await?;
+    let cache_dir = tempfile::tempdir()?;
+    let verifier = BytecodeSourceVerifier::new_with_cache(
+        client.read_api(),
+        false,
+        Box::new(FsPackageCache::new(cache_dir.path())),
+    );
+
+    // First run fetches `b` live and populates the cache.
+    verifier.verify_package_deps(&a_pkg.package).await.unwrap();
+
+    // Second run is served entirely from the cache, and still succeeds.
+    verifier.verify_package_deps(&a_pkg.package).await.unwrap();
+
+    Ok(())
+}
+
+#[tokio::test]
+async fn verify_package_deps_with_custom_concurrency_limit() -> anyhow::Result<()> {
+    let mut cluster = TestClusterBuilder::new().build().await?;
+    let sender = cluster.get_address_0();
+    let context = &mut cluster.wallet;
+
+    let b_ref = {
+        let fixtures = tempfile::tempdir()?;
+        let b_src = copy_package(&fixtures, "b", [("b", SuiAddress::ZERO)]).await?;
+        publish_package(context, sender, b_src).await
+    };
+
+    let a_pkg = {
+        let fixtures = tempfile::tempdir()?;
+        let b_id = b_ref.0.into();
+        copy_package(&fixtures, "b", [("b", b_id)]).await?;
+        let a_src = copy_package(&fixtures, "a", [("a", SuiAddress::ZERO), ("b", b_id)]).await?;
+        compile_package(a_src)
+    };
+
+    let client = context.get_client().await?;
+    let verifier = BytecodeSourceVerifier::new(client.read_api(), false).with_concurrency_limit(1);
+
+    // A concurrency limit of 1 serializes dependency fetches, but the result is identical to the
+    // default concurrency limit.
+    verifier.verify_package_deps(&a_pkg.package).await.unwrap();
+
+    Ok(())
+}
+
+#[tokio::test]
+async fn verify_package_deps_concurrency_limit_still_detects_mismatch() -> anyhow::Result<()> {
+    let mut cluster = TestClusterBuilder::new().build().await?;
+    let sender = cluster.get_address_0();
+    let context = &mut cluster.wallet;
+
+    let b_ref = {
+        let fixtures = tempfile::tempdir()?;
+        let b_src = copy_package(&fixtures, "b", [("b", SuiAddress::ZERO)]).await?;
+
+        let c_path = b_src.join("sources").join("c.move");
+        let c_file = tokio::fs::read_to_string(&c_path)
+            .await?
+            .replace("43", "44");
+        tokio::fs::write(&c_path, c_file).await?;
+
+        publish_package(context, sender, b_src).await
+    };
+
+    let a_pkg = {
+        let fixtures = tempfile::tempdir()?;
+        let b_id = b_ref.0.into();
+        copy_package(&fixtures, "b", [("b", b_id)]).await?;
+        let a_src = copy_package(&fixtures, "a", [("a", SuiAddress::ZERO), ("b", b_id)]).await?;
+        compile_package(a_src)
+    };
+
+    let client = context.get_client().await?;
+    let verifier = BytecodeSourceVerifier::new(client.read_api(), false).with_concurrency_limit(1);
+
+    let Err(err) = verifier.verify_package_deps(&a_pkg.package).await else {
+        panic!("Expected verification to fail");
+    };
+
+    assert!(matches!(
+        err,
+        SourceVerificationError::ModuleBytecodeMismatch { .. }
+    ));
+
+    Ok(())
+}
+
+#[tokio::test]
+async fn verify_package_collect_success() -> anyhow::Result<()> {
+    let mut cluster = TestClusterBuilder::new().build().await?;
+    let sender = cluster.get_address_0();
+    let context = &mut cluster.wallet;
+
+    let b_ref = {
+        let fixtures = tempfile::tempdir()?;
+        let b_src = copy_package(&fixtures, "b", [("b", SuiAddress::ZERO)]).await?;
+        publish_package(context, sender, b_src).await
+    };
+
+    let a_pkg = {
+        let fixtures = tempfile::tempdir()?;
+        let b_id = b_ref.0.into();
+        copy_package(&fixtures, "b", [("b", b_id)]).await?;
+        let a_src = copy_package(&fixtures, "a", [("a", SuiAddress::ZERO), ("b", b_id)]).await?;
+        compile_package(a_src)
+    };
+
+    let client = context.get_client().await?;
+    let verifier = BytecodeSourceVerifier::new(client.read_api(), false);
+
+    verifier
+        .verify_package_collect(&a_pkg.package, /* verify_deps */ true, SourceMode::Skip)
+        .await
+        .unwrap();
+
+    Ok(())
+}
+
+#[tokio::test]
+async fn verify_package_collect_reports_all_dependency_failures() -> anyhow::Result<()> {
+    let mut cluster = TestClusterBuilder::new().build().await?;
+    let sender = cluster.get_address_0();
+    let context = &mut cluster.wallet;
+
+    let b_ref = {
+        let fixtures = tempfile::tempdir()?;
+        let b_src = copy_package(&fixtures, "b", [("b", SuiAddress::ZERO)]).await?;
+
+        // One module mismatches...
+        let c_path = b_src.join("sources").join("c.move");
+        let c_file = tokio::fs::read_to_string(&c_path)
+            .await?
+            .replace("43", "44");
+        tokio::fs::write(&c_path, c_file).await?;
+
+        // ...and another is missing entirely from the on-chain package.
+        tokio::fs::remove_file(b_src.join("sources").join("d.move")).await?;
+
+        publish_package(context, sender, b_src).await
+    };
+
+    let a_pkg = {
+        let fixtures = tempfile::tempdir()?;
+        let b_id = b_ref.0.into();
+        copy_package(&fixtures, "b", [("b", b_id)]).await?;
+        let a_src = copy_package(&fixtures, "a", [("a", SuiAddress::ZERO), ("b", b_id)]).await?;
+        compile_package(a_src)
+    };
+
+    let client = context.get_client().await?;
+    let verifier = BytecodeSourceVerifier::new(client.read_api(), false);
+
+    let Err(errors) = verifier
+        .verify_package_collect(&a_pkg.package, /* verify_deps */ true, SourceMode::Skip)
+        .await
+    else {
+        panic!("Expected verification to fail");
+    };
+
+    assert_eq!(errors.len(), 2);
+    assert!(errors
+        .iter()
+        .any(|e| matches!(e, SourceVerificationError::ModuleBytecodeMismatch { .. })));
+    assert!(errors
+        .iter()
+        .any(|e| matches!(e, SourceVerificationError::OnChainDependencyNotFound { .. })));
+
+    Ok(())
+}
+
+#[tokio::test]
+async fn verify_against_snapshot_store_success() -> anyhow::Result<()> {
+    let b_addr = SuiAddress::random_for_testing_only();
+
+    let b_pkg = {
+        let fixtures = tempfile::tempdir()?;
+        let b_src = copy_package(&fixtures, "b", [("b", b_addr)]).await?;
+        compile_package(b_src)
+    };
+
+    let a_pkg = {
+        let fixtures = tempfile::tempdir()?;
+        copy_package(&fixtures, "b", [("b", b_addr)]).await?;
+        let a_src = copy_package(&fixtures, "a", [("a", SuiAddress::ZERO), ("b", b_addr)]).await?;
+        compile_package(a_src)
+    };
+
+    let snapshot = tempfile::tempdir()?;
+    write_snapshot_package(snapshot.path(), b_addr.into(), &b_pkg);
+    fs::write(snapshot.path().join("chain_id"), "offline-test")?;
+
+    let verifier = BytecodeSourceVerifier::with_store(
+        Box::new(SnapshotPackageStore::new(snapshot.path())),
+        false,
+    );
+
+    verifier.verify_package_deps(&a_pkg.package).await.unwrap();
+
+    Ok(())
+}
+
+#[tokio::test]
+async fn verify_against_snapshot_store_missing_package() -> anyhow::Result<()> {
+    let b_addr = SuiAddress::random_for_testing_only();
+
+    let a_pkg = {
+        let fixtures = tempfile::tempdir()?;
+        copy_package(&fixtures, "b", [("b", b_addr)]).await?;
+        let a_src = copy_package(&fixtures, "a", [("a", SuiAddress::ZERO), ("b", b_addr)]).await?;
+        compile_package(a_src)
+    };
+
+    let snapshot = tempfile::tempdir()?;
+    fs::write(snapshot.path().join("chain_id"), "offline-test")?;
+    // No package directory is written for `b_addr` -- this snapshot just doesn't cover it.
+
+    let verifier = BytecodeSourceVerifier::with_store(
+        Box::new(SnapshotPackageStore::new(snapshot.path())),
+        false,
+    );
+
+    let Err(err) = verifier.verify_package_deps(&a_pkg.package).await else {
+        panic!("Expected verification to fail");
+    };
+
+    assert!(matches!(
+        err,
+        SourceVerificationError::SnapshotPackageNotFound(..)
+    ));
+
+    Ok(())
+}
+
+#[tokio::test]
+async fn snapshot_store_missing_chain_id_reports_snapshot_error() -> anyhow::Result<()> {
+    let snapshot = tempfile::tempdir()?;
+    // No `chain_id` file is written.
+
+    let store = SnapshotPackageStore::new(snapshot.path());
+
+    let err = store.chain_identifier().await.unwrap_err();
+    assert!(matches!(err, SourceVerificationError::SnapshotIoFailure(_)));
+
+    Ok(())
+}
+
+#[tokio::test]
+async fn from_addr_dispatches_file_scheme_to_snapshot_store() -> anyhow::Result<()> {
+    let b_addr = SuiAddress::random_for_testing_only();
+
+    let b_pkg = {
+        let fixtures = tempfile::tempdir()?;
+        let b_src = copy_package(&fixtures, "b", [("b", b_addr)]).await?;
+        compile_package(b_src)
+    };
+
+    let a_pkg = {
+        let fixtures = tempfile::tempdir()?;
+        copy_package(&fixtures, "b", [("b", b_addr)]).await?;
+        let a_src = copy_package(&fixtures, "a", [("a", SuiAddress::ZERO), ("b", b_addr)]).await?;
+        compile_package(a_src)
+    };
+
+    let snapshot = tempfile::tempdir()?;
+    write_snapshot_package(snapshot.path(), b_addr.into(), &b_pkg);
+    fs::write(snapshot.path().join("chain_id"), "offline-test")?;
+
+    let verifier = BytecodeSourceVerifier::from_addr(
+        &format!("file://{}", snapshot.path().display()),
+        false,
+    )
+    .await
+    .unwrap();
+
+    verifier.verify_package_deps(&a_pkg.package).await.unwrap();
+
+    Ok(())
+}
+
+#[tokio::test]
+async fn from_addr_rejects_unsupported_scheme() {
+    let err = BytecodeSourceVerifier::from_addr("ftp://example.com", false)
+        .await
+        .unwrap_err();
+
+    assert!(matches!(
+        err,
+        SourceVerificationError::UnsupportedStoreScheme(_)
+    ));
+}
+
+/// Write out `pkg`'s root modules as a snapshot package directory under `root`, in the layout
+/// [`SnapshotPackageStore`] expects -- an `object_ref.toml` plus one `.mv` file per module.
+fn write_snapshot_package(root: &Path, address: AccountAddress, pkg: &CompiledPackage) {
+    let dir = root.join(address.to_canonical_string(true));
+    let modules_dir = dir.join("modules");
+    fs::create_dir_all(&modules_dir).unwrap();
+
+    fs::write(
+        dir.join("object_ref.toml"),
+        format!(
+            "object_id = \"{address}\"\nversion = 1\ndigest = \"{}\"\n",
+            ObjectDigest::random()
+        ),
+    )
+    .unwrap();
+
+    for unit in pkg.package.root_modules() {
+        let mut bytes = Vec::new();
+        unit.unit.module.serialize(&mut bytes).unwrap();
+        let name = unit.unit.module.self_id().name().to_string();
+        fs::write(modules_dir.join(format!("{name}.mv")), bytes).unwrap();
+    }
+}
+
 /// Compile the package at absolute path `package`.
 fn compile_package(package: impl AsRef<Path>) -> CompiledPackage {
     sui_framework::build_move_package(package.as_ref(), BuildConfig::new_for_testing()).unwrap()